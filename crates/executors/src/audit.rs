@@ -0,0 +1,284 @@
+// Structured, queryable audit trail of tool-approval decisions.
+//
+// `AuditLog::record` is non-blocking by design: it pushes onto a bounded
+// channel and returns immediately, while a background task drains the
+// channel in batches into a `BTreeMap` keyed by hour-wide time bucket (a
+// stand-in for a hypertable chunk) so range queries like "last 24h" only
+// walk the buckets they overlap. `AuditLog::global()` gives every executor
+// a shared instance to record into and query even when nothing explicitly
+// constructs and attaches one.
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex, OnceLock},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+
+/// Final disposition of a tool-approval decision.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ApprovalVerdict {
+    Approved,
+    Denied,
+    AutoApproved,
+}
+
+/// One recorded approval decision.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AuditEvent {
+    pub timestamp_secs: i64,
+    pub session_id: String,
+    pub executor_kind: String,
+    pub tool_name: String,
+    pub matcher: String,
+    pub callback_id: String,
+    pub verdict: ApprovalVerdict,
+}
+
+impl AuditEvent {
+    fn now(session_id: &str, executor_kind: &str, tool_name: &str, matcher: &str, callback_id: &str, verdict: ApprovalVerdict) -> Self {
+        let timestamp_secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+        Self {
+            timestamp_secs,
+            session_id: session_id.to_string(),
+            executor_kind: executor_kind.to_string(),
+            tool_name: tool_name.to_string(),
+            matcher: matcher.to_string(),
+            callback_id: callback_id.to_string(),
+            verdict,
+        }
+    }
+}
+
+/// Filters for querying recorded events.
+#[derive(Clone, Debug, Default)]
+pub struct AuditQuery {
+    pub session_id: Option<String>,
+    pub tool_name: Option<String>,
+    pub verdict: Option<ApprovalVerdict>,
+    /// Inclusive lower bound on `timestamp_secs`.
+    pub since_secs: Option<i64>,
+}
+
+impl AuditQuery {
+    fn matches(&self, event: &AuditEvent) -> bool {
+        self.session_id.as_deref().is_none_or(|s| s == event.session_id)
+            && self.tool_name.as_deref().is_none_or(|t| t == event.tool_name)
+            && self.verdict.is_none_or(|v| v == event.verdict)
+            && self.since_secs.is_none_or(|since| event.timestamp_secs >= since)
+    }
+}
+
+/// Coarse bucket width used to key the conceptual hypertable, so "last 24h"
+/// style range queries only need to scan the buckets they overlap.
+const BUCKET_SECS: i64 = 3600;
+
+fn bucket_for(timestamp_secs: i64) -> i64 {
+    timestamp_secs.div_euclid(BUCKET_SECS)
+}
+
+#[derive(Default)]
+struct AuditStore {
+    buckets: BTreeMap<i64, Vec<AuditEvent>>,
+}
+
+impl AuditStore {
+    fn insert(&mut self, event: AuditEvent) {
+        self.buckets.entry(bucket_for(event.timestamp_secs)).or_default().push(event);
+    }
+
+    fn query(&self, query: &AuditQuery) -> Vec<AuditEvent> {
+        let from_bucket = query.since_secs.map(bucket_for).unwrap_or(i64::MIN);
+        self.buckets
+            .range(from_bucket..)
+            .flat_map(|(_, events)| events.iter())
+            .filter(|event| query.matches(event))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Handle for recording and querying tool-approval decisions.
+///
+/// Events are pushed onto a bounded channel; a background task drains it in
+/// batches and writes into the time-bucketed store. A full channel drops the
+/// event being recorded rather than blocking the approval path.
+#[derive(Clone)]
+pub struct AuditLog {
+    sender: mpsc::Sender<AuditEvent>,
+    store: Arc<Mutex<AuditStore>>,
+}
+
+const CHANNEL_CAPACITY: usize = 1024;
+const FLUSH_BATCH_SIZE: usize = 64;
+
+impl AuditLog {
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::channel(CHANNEL_CAPACITY);
+        let store = Arc::new(Mutex::new(AuditStore::default()));
+
+        tokio::spawn(Self::flush_loop(receiver, store.clone()));
+
+        Self { sender, store }
+    }
+
+    async fn flush_loop(mut receiver: mpsc::Receiver<AuditEvent>, store: Arc<Mutex<AuditStore>>) {
+        let mut batch = Vec::with_capacity(FLUSH_BATCH_SIZE);
+        while receiver.recv_many(&mut batch, FLUSH_BATCH_SIZE).await > 0 {
+            let mut store = store.lock().unwrap();
+            for event in batch.drain(..) {
+                store.insert(event);
+            }
+        }
+    }
+
+    /// Record a decision. Never blocks the caller: if the buffer is full the
+    /// event is dropped and a warning is logged rather than stalling the
+    /// approval path.
+    pub fn record(&self, event: AuditEvent) {
+        if let Err(err) = self.sender.try_send(event) {
+            tracing::warn!("Dropping audit event, buffer full: {err}");
+        }
+    }
+
+    pub fn query(&self, query: &AuditQuery) -> Vec<AuditEvent> {
+        self.store.lock().unwrap().query(query)
+    }
+}
+
+impl Default for AuditLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+static GLOBAL_AUDIT_LOG: OnceLock<AuditLog> = OnceLock::new();
+
+impl AuditLog {
+    /// The process-wide audit log executors fall back to when nothing calls
+    /// `use_audit_log` to attach a specific instance (e.g. a test harness
+    /// wanting an isolated one), so decisions are recorded and queryable
+    /// even without any external wiring.
+    pub fn global() -> AuditLog {
+        GLOBAL_AUDIT_LOG.get_or_init(AuditLog::new).clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(session_id: &str, tool_name: &str, timestamp_secs: i64, verdict: ApprovalVerdict) -> AuditEvent {
+        AuditEvent {
+            timestamp_secs,
+            session_id: session_id.to_string(),
+            executor_kind: "codebuddy".to_string(),
+            tool_name: tool_name.to_string(),
+            matcher: ".*".to_string(),
+            callback_id: "auto_approve".to_string(),
+            verdict,
+        }
+    }
+
+    #[test]
+    fn bucket_for_groups_by_hour() {
+        assert_eq!(bucket_for(0), 0);
+        assert_eq!(bucket_for(BUCKET_SECS - 1), 0);
+        assert_eq!(bucket_for(BUCKET_SECS), 1);
+    }
+
+    #[test]
+    fn query_matches_filters_on_every_field() {
+        let approved = event("session-a", "Bash", 100, ApprovalVerdict::Approved);
+
+        let matching = AuditQuery {
+            session_id: Some("session-a".to_string()),
+            tool_name: Some("Bash".to_string()),
+            verdict: Some(ApprovalVerdict::Approved),
+            since_secs: Some(50),
+        };
+        assert!(matching.matches(&approved));
+
+        let wrong_session = AuditQuery {
+            session_id: Some("session-b".to_string()),
+            ..AuditQuery::default()
+        };
+        assert!(!wrong_session.matches(&approved));
+
+        let too_recent = AuditQuery {
+            since_secs: Some(200),
+            ..AuditQuery::default()
+        };
+        assert!(!too_recent.matches(&approved));
+    }
+
+    #[tokio::test]
+    async fn record_auto_approve_policy_leaves_tool_name_blank_not_matcher() {
+        let audit_log = AuditLog::new();
+        let hooks = serde_json::json!({
+            "PreToolUse": [
+                { "matcher": ".*", "hookCallbackIds": ["auto_approve"] },
+                { "matcher": "^ExitPlanMode$", "hookCallbackIds": ["tool_approval"] },
+            ]
+        });
+
+        record_auto_approve_policy(&audit_log, "session-a", "codebuddy", &hooks, "auto_approve");
+        // `record` only enqueues; give the background flush loop a turn to
+        // drain it into the store before querying.
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+
+        let events = audit_log.query(&AuditQuery::default());
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].matcher, ".*");
+        assert_eq!(events[0].tool_name, NO_CONCRETE_TOOL);
+    }
+}
+
+/// `tool_name` recorded for a policy-level event: `record_auto_approve_policy`
+/// runs once per hook registration, before any concrete tool call exists, so
+/// there's no real tool name to put there yet. Left distinct from `matcher`
+/// (which does hold the regex) so an `AuditQuery { tool_name: Some(..) }`
+/// filter can't be fooled into matching a rule by its pattern text.
+const NO_CONCRETE_TOOL: &str = "";
+
+/// Records one event per `PreToolUse` matcher rule that resolves to an
+/// unconditional auto-approve for every tool it matches (i.e. rules
+/// targeting `AUTO_APPROVE_CALLBACK_ID`). Rules that route matching tools to
+/// `tool_approval` aren't recorded here since no verdict exists yet — that
+/// decision is made later, per call, by `ExecutorApprovalService` itself.
+pub fn record_auto_approve_policy(
+    audit_log: &AuditLog,
+    session_id: &str,
+    executor_kind: &str,
+    hooks: &serde_json::Value,
+    auto_approve_callback_id: &str,
+) {
+    let Some(rules) = hooks.get("PreToolUse").and_then(|v| v.as_array()) else {
+        return;
+    };
+    for rule in rules {
+        let Some(callback_ids) = rule.get("hookCallbackIds").and_then(|v| v.as_array()) else {
+            continue;
+        };
+        let is_auto_approve = callback_ids
+            .iter()
+            .any(|id| id.as_str() == Some(auto_approve_callback_id));
+        if !is_auto_approve {
+            continue;
+        }
+        let matcher = rule.get("matcher").and_then(|v| v.as_str()).unwrap_or("*");
+        audit_log.record(AuditEvent::now(
+            session_id,
+            executor_kind,
+            NO_CONCRETE_TOOL,
+            matcher,
+            auto_approve_callback_id,
+            ApprovalVerdict::AutoApproved,
+        ));
+    }
+}
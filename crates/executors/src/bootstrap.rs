@@ -0,0 +1,255 @@
+// Auto-bootstrap and version-pin the `codebuddy` CLI.
+//
+// The cache layout is `<user cache dir>/vibe-kanban/codebuddy/<version>/codebuddy`, so a
+// binary found under the pinned version's directory is trusted to be that
+// version unless `--version` proves otherwise (corrupt download, manual
+// tampering). `resolve_binary_path()` is what `base_command()` calls, but
+// only ever does the real work — `ensure_up_to_date` plus its
+// `--version` exec — once per process: the result is cached in
+// `RESOLVED_BINARY` so a stale or broken cache self-heals the first time
+// it's needed, not on every message turn of every run afterwards.
+
+use std::path::{Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+use tokio::sync::OnceCell;
+
+/// Minimum version of a `codebuddy` on PATH that `get_availability_info`
+/// reports as an installation.
+pub const MIN_REQUIRED_VERSION: &str = "1.0.0";
+
+/// Version this build of vibe-kanban is pinned to and will install.
+pub const PINNED_VERSION: &str = "1.4.2";
+
+#[derive(Debug, thiserror::Error)]
+pub enum BootstrapError {
+    #[error("unsupported target triple: {os}/{arch}")]
+    UnsupportedTarget { os: String, arch: String },
+    #[error("failed to download codebuddy artifact: {0}")]
+    Download(#[from] reqwest::Error),
+    #[error("checksum mismatch for downloaded codebuddy artifact")]
+    ChecksumMismatch,
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+fn cache_dir() -> PathBuf {
+    dirs::cache_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("vibe-kanban")
+        .join("codebuddy")
+}
+
+/// Synchronous check for whether the pinned binary is already cached,
+/// usable from the non-async `get_availability_info` trait method.
+pub fn cached_binary_path_if_present() -> Option<PathBuf> {
+    let path = cached_binary_path(PINNED_VERSION);
+    path.exists().then_some(path)
+}
+
+fn cached_binary_path(version: &str) -> PathBuf {
+    cache_dir().join(version).join(if cfg!(windows) {
+        "codebuddy.exe"
+    } else {
+        "codebuddy"
+    })
+}
+
+/// Maps the current `(os, arch)` to the artifact name published alongside
+/// each release, mirroring the naming scheme remote-agent tooling already
+/// uses to fetch its matching server binary.
+fn artifact_name(version: &str) -> Result<String, BootstrapError> {
+    let os = std::env::consts::OS;
+    let arch = std::env::consts::ARCH;
+    let (os_label, ext) = match os {
+        "macos" => ("darwin", "tar.gz"),
+        "linux" => ("linux", "tar.gz"),
+        "windows" => ("windows", "zip"),
+        _ => {
+            return Err(BootstrapError::UnsupportedTarget {
+                os: os.to_string(),
+                arch: arch.to_string(),
+            });
+        }
+    };
+    Ok(format!("codebuddy-{version}-{os_label}-{arch}.{ext}"))
+}
+
+fn download_url(version: &str, artifact: &str) -> String {
+    format!("https://cdn.codebuddy.dev/releases/{version}/{artifact}")
+}
+
+fn checksum_url(version: &str, artifact: &str) -> String {
+    format!("https://cdn.codebuddy.dev/releases/{version}/{artifact}.sha256")
+}
+
+/// Blocking variant, for the non-async `get_availability_info`/
+/// `path_install_is_current` call sites — rare enough (one check per
+/// availability probe, not per spawn) that blocking a worker thread is
+/// acceptable there.
+fn installed_version_blocking(binary: &Path) -> Option<String> {
+    let output = std::process::Command::new(binary).arg("--version").output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Non-blocking variant for `ensure_up_to_date`, which runs on the async
+/// spawn path: `installed_version_blocking`'s synchronous exec+wait would
+/// otherwise stall a tokio worker thread on every resolve.
+async fn installed_version(binary: &Path) -> Option<String> {
+    let output = tokio::process::Command::new(binary).arg("--version").output().await.ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Whether a `codebuddy` found on PATH (as opposed to our own cache) is new
+/// enough for `get_availability_info` to count the manual install as found.
+/// Spawning still goes through `resolve_binary_path`, which uses the pinned
+/// cached copy and only falls back to PATH when the bootstrap fails.
+pub fn path_install_is_current() -> bool {
+    installed_version_blocking(Path::new("codebuddy"))
+        .is_some_and(|version| is_at_least(&version, MIN_REQUIRED_VERSION))
+}
+
+fn is_at_least(version: &str, minimum: &str) -> bool {
+    let parse = |v: &str| {
+        v.trim_start_matches('v')
+            .split('.')
+            .map(|part| part.parse::<u32>().unwrap_or(0))
+            .collect::<Vec<_>>()
+    };
+    parse(version) >= parse(minimum)
+}
+
+/// Downloads and verifies the pinned `codebuddy` artifact into the cache
+/// directory, returning the path to the now-executable binary.
+pub async fn ensure_installed() -> Result<PathBuf, BootstrapError> {
+    let binary_path = cached_binary_path(PINNED_VERSION);
+    if binary_path.exists() {
+        return Ok(binary_path);
+    }
+
+    let artifact = artifact_name(PINNED_VERSION)?;
+    let bytes = reqwest::get(download_url(PINNED_VERSION, &artifact))
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+
+    let expected_checksum = reqwest::get(checksum_url(PINNED_VERSION, &artifact))
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+    let expected_checksum = expected_checksum.split_whitespace().next().unwrap_or_default();
+
+    let mut hasher = Sha256::new();
+    hasher.update(&bytes);
+    let actual_checksum = format!("{:x}", hasher.finalize());
+    if actual_checksum != expected_checksum {
+        return Err(BootstrapError::ChecksumMismatch);
+    }
+
+    let version_dir = binary_path.parent().expect("binary path has a parent");
+    std::fs::create_dir_all(version_dir)?;
+    extract_archive(&bytes, &artifact, version_dir)?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mut perms = std::fs::metadata(&binary_path)?.permissions();
+        perms.set_mode(0o755);
+        std::fs::set_permissions(&binary_path, perms)?;
+    }
+
+    Ok(binary_path)
+}
+
+fn extract_archive(bytes: &[u8], artifact: &str, dest: &Path) -> Result<(), BootstrapError> {
+    if artifact.ends_with(".tar.gz") {
+        let tar = flate2::read::GzDecoder::new(bytes);
+        tar::Archive::new(tar).unpack(dest)?;
+    } else {
+        zip::ZipArchive::new(std::io::Cursor::new(bytes))
+            .map_err(std::io::Error::other)?
+            .extract(dest)
+            .map_err(std::io::Error::other)?;
+    }
+    Ok(())
+}
+
+/// Resolved once per process and reused for every subsequent spawn; see
+/// `resolve_binary_path`.
+static RESOLVED_BINARY: OnceCell<PathBuf> = OnceCell::const_new();
+
+/// Resolves the binary `base_command()` should invoke, re-bootstrapping the
+/// cache first if it's missing or stale. Falls back to a bare `codebuddy`
+/// lookup on PATH if the bootstrap itself fails (e.g. no network), so
+/// existing manual installs keep working. Only does this work once per
+/// process — every spawn after the first reuses the cached result instead
+/// of re-checking the binary's version on every message turn.
+pub async fn resolve_binary_path() -> PathBuf {
+    RESOLVED_BINARY
+        .get_or_init(|| async {
+            match ensure_up_to_date().await {
+                Ok(path) => path,
+                Err(err) => {
+                    tracing::warn!("Failed to bootstrap codebuddy, falling back to PATH lookup: {err}");
+                    PathBuf::from("codebuddy")
+                }
+            }
+        })
+        .await
+        .clone()
+}
+
+/// Re-checks the cached binary's own `--version` output against
+/// `PINNED_VERSION` (not just its presence on disk) and re-downloads it if
+/// it's missing or reports an older version, so a stale or corrupted cache
+/// self-heals the next time it's used.
+pub async fn ensure_up_to_date() -> Result<PathBuf, BootstrapError> {
+    let cached = cached_binary_path(PINNED_VERSION);
+    if let Some(version) = installed_version(&cached).await
+        && is_at_least(&version, PINNED_VERSION)
+    {
+        return Ok(cached);
+    }
+    // `ensure_installed` returns any existing file as-is, so a stale or
+    // broken binary has to be removed for the re-download to happen.
+    if cached.exists() {
+        std::fs::remove_file(&cached)?;
+    }
+    ensure_installed().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_at_least_accepts_equal_and_newer() {
+        assert!(is_at_least("1.4.2", "1.4.2"));
+        assert!(is_at_least("1.5.0", "1.4.2"));
+        assert!(is_at_least("2.0.0", "1.4.2"));
+    }
+
+    #[test]
+    fn is_at_least_rejects_older() {
+        assert!(!is_at_least("1.4.1", "1.4.2"));
+        assert!(!is_at_least("0.9.9", "1.0.0"));
+    }
+
+    #[test]
+    fn is_at_least_tolerates_a_leading_v() {
+        assert!(is_at_least("v1.4.2", "1.4.2"));
+    }
+
+    #[test]
+    fn is_at_least_treats_unparseable_segments_as_zero() {
+        assert!(!is_at_least("abc", "1.0.0"));
+    }
+}
@@ -7,14 +7,17 @@ use async_trait::async_trait;
 use command_group::AsyncCommandGroup;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use tokio::process::Command;
 use ts_rs::TS;
 use workspace_utils::msg_store::MsgStore;
 use std::process::Stdio;
 use derivative::Derivative;
+use tokio::io::AsyncWriteExt;
+use uuid::Uuid;
 
 use crate::{
     approvals::ExecutorApprovalService,
+    audit::{self, AuditLog},
+    bootstrap,
     command::{CmdOverrides, CommandBuildError, CommandBuilder, CommandParts, apply_overrides},
     env::ExecutionEnv,
     executors::{
@@ -27,11 +30,18 @@ use crate::{
     },
     logs::stderr_processor::normalize_stderr_logs,
     logs::utils::EntryIndexProvider,
+    secrets::{self, SecretRedactionSet},
     stdout_dup::create_stdout_pipe_writer,
+    transport::{RemoteTarget, SshTransport, transport_for},
 };
 
-fn base_command() -> &'static str {
-    "codebuddy"
+/// Resolves the binary to invoke, preferring the cached, version-pinned
+/// install over a bare `codebuddy` lookup on PATH.
+async fn base_command() -> String {
+    bootstrap::resolve_binary_path()
+        .await
+        .to_string_lossy()
+        .into_owned()
 }
 
 #[derive(Derivative, Clone, Serialize, Deserialize, TS, JsonSchema)]
@@ -44,6 +54,16 @@ pub struct CodeBuddy {
     pub plan: Option<bool>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub approvals: Option<bool>,
+    /// When set, the agent is spawned on this host over SSH instead of
+    /// locally; working tree and auth file then only need to exist there.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote: Option<RemoteTarget>,
+    /// Named secrets this run needs granted into its environment. Each is
+    /// resolved through `ExecutorApprovalService` before spawn, so the
+    /// value reaches the child via its environment rather than a prompt or
+    /// CLI argument.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub secrets: Vec<String>,
     #[serde(flatten)]
     pub cmd: CmdOverrides,
 
@@ -51,11 +71,21 @@ pub struct CodeBuddy {
     #[ts(skip)]
     #[derivative(Debug = "ignore", PartialEq = "ignore")]
     approvals_service: Option<Arc<dyn ExecutorApprovalService>>,
+
+    #[serde(skip)]
+    #[ts(skip)]
+    #[derivative(Debug = "ignore", PartialEq = "ignore")]
+    audit_log: Option<Arc<AuditLog>>,
+
+    #[serde(skip)]
+    #[ts(skip)]
+    #[derivative(Debug = "ignore", PartialEq = "ignore")]
+    secret_redaction: SecretRedactionSet,
 }
 
 impl CodeBuddy {
     async fn build_command_builder(&self) -> Result<CommandBuilder, CommandBuildError> {
-        let mut builder = CommandBuilder::new(base_command()).params(["-p"]);
+        let mut builder = CommandBuilder::new(base_command().await).params(["-p"]);
 
         let plan = self.plan.unwrap_or(false);
         let approvals = self.approvals.unwrap_or(false);
@@ -100,6 +130,13 @@ impl CodeBuddy {
             );
         }
 
+        // No `SecretRequest` hook is advertised here: `self.secrets` is
+        // resolved up front by `resolve_secret_env` before spawn, and
+        // nothing in this tree dispatches a mid-run `SecretRequest`
+        // control-protocol callback to `secrets::handle_secret_request` (see
+        // secrets.rs), so advertising the hook would just make the agent
+        // wait forever on a request nobody answers.
+
         if self.plan.unwrap_or(false) {
             hooks.insert(
                 "PreToolUse".to_string(),
@@ -139,6 +176,37 @@ impl CodeBuddy {
 
         Some(serde_json::Value::Object(hooks))
     }
+
+    /// Attach the shared audit log so approval decisions made for this run
+    /// are recorded alongside every other executor's.
+    pub fn use_audit_log(&mut self, audit_log: Arc<AuditLog>) {
+        self.audit_log = Some(audit_log);
+    }
+
+    /// Resolves every name in `self.secrets` through the approval service
+    /// before spawn, so a granted value reaches the child via its
+    /// environment and is redacted rather than ever touching the prompt.
+    /// Missing `approvals_service`, a deny, or an error/cancel all just
+    /// drop that one secret rather than failing the whole spawn.
+    async fn resolve_secret_env(&self) -> std::collections::HashMap<String, String> {
+        let mut env_overrides = std::collections::HashMap::new();
+        let Some(approvals) = &self.approvals_service else {
+            if !self.secrets.is_empty() {
+                tracing::warn!("Secrets requested but no approval service is configured");
+            }
+            return env_overrides;
+        };
+
+        for name in &self.secrets {
+            let request = secrets::SecretRequest { name: name.clone() };
+            match secrets::handle_secret_request(request, approvals, &self.secret_redaction, &mut env_overrides).await
+            {
+                secrets::SecretGrantResult::Granted => {}
+                result => tracing::warn!("Secret '{name}' not granted: {result:?}"),
+            }
+        }
+        env_overrides
+    }
 }
 
 #[async_trait]
@@ -155,7 +223,11 @@ impl StandardCodingAgentExecutor for CodeBuddy {
     ) -> Result<SpawnedChild, ExecutorError> {
         let command_builder = self.build_command_builder().await?;
         let command_parts = command_builder.build_initial()?;
-        self.spawn_internal(current_dir, prompt, command_parts, env)
+        // No prior session to key the audit trail on: each fresh run gets
+        // its own id, so repeated retries against the same worktree don't
+        // collapse into one audit "session".
+        let audit_session_id = Uuid::new_v4().to_string();
+        self.spawn_internal(current_dir, prompt, command_parts, env, audit_session_id)
             .await
     }
 
@@ -172,7 +244,7 @@ impl StandardCodingAgentExecutor for CodeBuddy {
             "--resume".to_string(),
             session_id.to_string(),
         ])?;
-        self.spawn_internal(current_dir, prompt, command_parts, env)
+        self.spawn_internal(current_dir, prompt, command_parts, env, session_id.to_string())
             .await
     }
 
@@ -180,15 +252,19 @@ impl StandardCodingAgentExecutor for CodeBuddy {
         let entry_index_provider = EntryIndexProvider::start_from(&msg_store);
 
         // Process stdout logs (CodeBuddy's JSON output - same format as Claude)
+        // Any value granted through `resolve_secret_env` is redacted before
+        // it reaches MsgStore, regardless of which line it surfaces on,
+        // except values too short for `SecretRedactionSet` to register.
         ClaudeLogProcessor::process_logs(
             msg_store.clone(),
             current_dir,
             entry_index_provider.clone(),
             HistoryStrategy::Default,
+            self.secret_redaction.clone(),
         );
 
         // Process stderr logs using the standard stderr processor
-        normalize_stderr_logs(msg_store, entry_index_provider);
+        normalize_stderr_logs(msg_store, entry_index_provider, self.secret_redaction.clone());
     }
 
     // MCP configuration methods - CodeBuddy uses similar config path
@@ -197,12 +273,24 @@ impl StandardCodingAgentExecutor for CodeBuddy {
     }
 
     fn get_availability_info(&self) -> AvailabilityInfo {
-        // Check if codebuddy command is available by checking common paths
-        let codebuddy_exists = std::process::Command::new("codebuddy")
-            .arg("--version")
-            .output()
-            .is_ok();
-        
+        // A remote target is a different machine entirely: probe it over
+        // SSH instead of checking anything on the local machine.
+        if let Some(remote) = &self.remote {
+            let transport = SshTransport::new(remote.clone());
+            return if transport.probe_available_blocking("codebuddy") {
+                AvailabilityInfo::InstallationFound
+            } else {
+                AvailabilityInfo::NotFound
+            };
+        }
+
+        // A sufficiently new codebuddy on PATH counts; otherwise fall back
+        // to whatever we've already bootstrapped into the cache directory
+        // (base_command() will trigger a fresh bootstrap on the next spawn
+        // if even that is missing).
+        let codebuddy_exists =
+            bootstrap::path_install_is_current() || bootstrap::cached_binary_path_if_present().is_some();
+
         if codebuddy_exists {
             // Check for auth file
             let auth_file_path = dirs::home_dir().map(|home| home.join(".codebuddy.json"));
@@ -230,36 +318,62 @@ impl CodeBuddy {
         prompt: &str,
         command_parts: CommandParts,
         env: &ExecutionEnv,
+        audit_session_id: String,
     ) -> Result<SpawnedChild, ExecutorError> {
         let (program_path, args) = command_parts.into_resolved().await?;
         let combined_prompt = self.append_prompt.combine_prompt(prompt);
 
-        let mut command = Command::new(program_path);
+        let secret_env = self.resolve_secret_env().await;
+
+        let transport = transport_for(self.remote.as_ref());
+        let stdin_env_preamble = transport.stdin_env_preamble(&secret_env);
+        let mut command =
+            transport.build_command(&program_path, &args, current_dir, env, &self.cmd, &secret_env)?;
         command
             .kill_on_drop(true)
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .current_dir(current_dir)
-            .args(&args);
-
-        env.clone()
-            .with_profile(&self.cmd)
-            .apply_to_command(&mut command);
+            .stderr(Stdio::piped());
 
         let mut child = command.group_spawn()?;
         let child_stdout = child.inner().stdout.take().ok_or_else(|| {
             ExecutorError::Io(std::io::Error::other("CodeBuddy missing stdout"))
         })?;
-        let child_stdin =
+        let mut child_stdin =
             child.inner().stdin.take().ok_or_else(|| {
                 ExecutorError::Io(std::io::Error::other("CodeBuddy missing stdin"))
             })?;
 
+        // Secrets never go on this (or the remote) command line; a transport
+        // that can't apply them directly (SSH) instead expects them as
+        // plain lines on stdin, ahead of any control-protocol traffic.
+        if !stdin_env_preamble.is_empty() {
+            let mut payload = stdin_env_preamble.join("\n");
+            payload.push_str("\n\n");
+            child_stdin.write_all(payload.as_bytes()).await.map_err(|e| {
+                ExecutorError::Io(std::io::Error::other(format!(
+                    "failed to write secret env preamble: {e}"
+                )))
+            })?;
+        }
+
         let new_stdout = create_stdout_pipe_writer(&mut child)?;
         let permission_mode = self.permission_mode();
         let hooks = self.get_hooks(env.commit_reminder);
 
+        // Falls back to the process-wide log so decisions are always
+        // recorded, even for a `CodeBuddy` nobody called `use_audit_log` on.
+        let audit_log = self.audit_log.clone().unwrap_or_else(|| Arc::new(AuditLog::global()));
+        if let Some(hooks) = &hooks {
+            audit::record_auto_approve_policy(
+                &audit_log,
+                &audit_session_id,
+                "codebuddy",
+                hooks,
+                AUTO_APPROVE_CALLBACK_ID,
+            );
+        }
+
         // Create interrupt channel for graceful shutdown
         let (interrupt_tx, interrupt_rx) = tokio::sync::oneshot::channel::<()>();
 
@@ -267,9 +381,16 @@ impl CodeBuddy {
         let prompt_clone = combined_prompt.clone();
         let approvals_clone = self.approvals_service.clone();
         let repo_context = env.repo_context.clone();
+        let secret_redaction = self.secret_redaction.clone();
         tokio::spawn(async move {
             let log_writer = LogWriter::new(new_stdout);
-            let client = ClaudeAgentClient::new(log_writer.clone(), approvals_clone, repo_context);
+            let client = ClaudeAgentClient::new(
+                log_writer.clone(),
+                approvals_clone,
+                repo_context,
+                Some((audit_log, "codebuddy", audit_session_id)),
+                secret_redaction,
+            );
             let protocol_peer =
                 ProtocolPeer::spawn(child_stdin, child_stdout, client.clone(), interrupt_rx);
 
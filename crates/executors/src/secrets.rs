@@ -0,0 +1,133 @@
+// Secret-request plumbing for the one grant path this tree actually drives:
+// `CodeBuddy::resolve_secret_env` calls `handle_secret_request` once per
+// name in `self.secrets` before spawn, so the approval service sees an
+// explicit grant/deny, exactly like a `tool_approval` decision, and
+// `SecretRedactionSet` is what keeps a granted value from then leaking back
+// out through stdout/stderr once it's in the child's environment.
+//
+// A mid-run `SecretRequest` control-protocol callback (the agent asking for
+// a credential we didn't know about up front) would reuse this same
+// `handle_secret_request`, but nothing in this tree dispatches one — the
+// control-protocol message loop that would receive it lives in
+// `ClaudeAgentClient` (`executors::claude::client`) — so `CodeBuddy`
+// deliberately does not advertise a `SecretRequest` hook to the agent.
+// Wiring that up is follow-on work for whoever owns that dispatch loop.
+
+use std::{
+    collections::HashSet,
+    sync::{Arc, RwLock},
+};
+
+/// Shortest value `SecretRedactionSet` will register for redaction. Below
+/// this, a granted secret (a single digit, "ok", part of an ordinary word)
+/// would blanket-replace unrelated substrings in every future log line with
+/// no way to tell a real redaction from collateral damage.
+const MIN_REDACTED_VALUE_LEN: usize = 8;
+
+/// Outcome of asking `ExecutorApprovalService` for a secret. Kept distinct
+/// from a plain bool so the agent can tell an explicit human "no" apart
+/// from the request never completing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SecretGrantResult {
+    Granted,
+    Denied,
+    Canceled,
+    Errored,
+}
+
+/// Tracks the secret values granted to a run so the log processors can
+/// redact them on sight, regardless of which tool call, stdout line, or
+/// stderr line they might otherwise leak through. Values shorter than
+/// `MIN_REDACTED_VALUE_LEN` are not tracked and so are not redacted.
+#[derive(Clone, Default)]
+pub struct SecretRedactionSet {
+    values: Arc<RwLock<HashSet<String>>>,
+}
+
+impl SecretRedactionSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn insert(&self, value: &str) {
+        if value.len() < MIN_REDACTED_VALUE_LEN {
+            tracing::warn!(
+                "Not registering a secret shorter than {MIN_REDACTED_VALUE_LEN} chars for log redaction"
+            );
+            return;
+        }
+        self.values.write().unwrap().insert(value.to_string());
+    }
+
+    /// Replaces every occurrence of a granted secret value with `***`.
+    /// Called by `normalize_logs`/`normalize_stderr_logs` before a line is
+    /// written to `MsgStore`.
+    pub fn redact(&self, line: &str) -> String {
+        let values = self.values.read().unwrap();
+        if values.is_empty() {
+            return line.to_string();
+        }
+        let mut redacted = line.to_string();
+        for value in values.iter() {
+            redacted = redacted.replace(value.as_str(), "***");
+        }
+        redacted
+    }
+}
+
+/// A single named secret request made by the agent.
+#[derive(Clone, Debug)]
+pub struct SecretRequest {
+    pub name: String,
+}
+
+/// Routes `request` through `approvals`, and on grant injects the value
+/// into `env_overrides` and records it for redaction (a value shorter than
+/// `MIN_REDACTED_VALUE_LEN` is still injected but not recorded). Returns the disposition
+/// so the caller can tell the agent whether to retry, ask for a different
+/// secret, or give up.
+pub async fn handle_secret_request(
+    request: SecretRequest,
+    approvals: &Arc<dyn crate::approvals::ExecutorApprovalService>,
+    redaction_set: &SecretRedactionSet,
+    env_overrides: &mut std::collections::HashMap<String, String>,
+) -> SecretGrantResult {
+    match approvals.request_secret(&request.name).await {
+        Ok(Some(value)) => {
+            redaction_set.insert(&value);
+            env_overrides.insert(request.name, value);
+            SecretGrantResult::Granted
+        }
+        Ok(None) => SecretGrantResult::Denied,
+        Err(crate::approvals::ApprovalError::Canceled) => SecretGrantResult::Canceled,
+        Err(_) => SecretGrantResult::Errored,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redact_replaces_every_occurrence_of_a_granted_value() {
+        let set = SecretRedactionSet::new();
+        set.insert("sk-super-secret-token");
+        assert_eq!(
+            set.redact("token=sk-super-secret-token, again sk-super-secret-token"),
+            "token=***, again ***"
+        );
+    }
+
+    #[test]
+    fn redact_is_a_no_op_with_nothing_registered() {
+        let set = SecretRedactionSet::new();
+        assert_eq!(set.redact("nothing to see here"), "nothing to see here");
+    }
+
+    #[test]
+    fn insert_rejects_values_shorter_than_the_minimum() {
+        let set = SecretRedactionSet::new();
+        set.insert("ok");
+        assert_eq!(set.redact("the status was ok"), "the status was ok");
+    }
+}
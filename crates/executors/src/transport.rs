@@ -0,0 +1,297 @@
+// Picks between running an executor's child process locally and running it
+// on a remote host over a plain (non-pty) SSH exec channel, so the child's
+// stdio pipes stay binary-clean stream-json either way.
+
+use std::{collections::HashMap, path::Path};
+
+use tokio::process::Command;
+
+use crate::{command::CmdOverrides, env::ExecutionEnv, executors::ExecutorError};
+
+/// Identity/host configuration for a remote worker, set on the executor
+/// (`CodeBuddy::remote`) so a task can target a machine other than the one
+/// vibe-kanban itself runs on. It is not carried in `CmdOverrides` or
+/// `ExecutionEnv`.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize, schemars::JsonSchema, ts_rs::TS)]
+#[ts(export)]
+pub struct RemoteTarget {
+    pub host: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity_file: Option<String>,
+}
+
+impl RemoteTarget {
+    fn ssh_destination(&self) -> String {
+        match &self.user {
+            Some(user) => format!("{user}@{}", self.host),
+            None => self.host.clone(),
+        }
+    }
+
+    /// Flags shared by every invocation of the `ssh` client for this target,
+    /// independent of whether the caller builds a `tokio` or `std` command.
+    fn connection_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(port) = self.port {
+            args.push("-p".to_string());
+            args.push(port.to_string());
+        }
+        if let Some(identity_file) = &self.identity_file {
+            args.push("-i".to_string());
+            args.push(identity_file.clone());
+        }
+        // No `-tt`: a pty merges the remote process's stderr into stdout and
+        // echoes stdin back into the output stream, which corrupts the
+        // stream-json framing `ProtocolPeer` depends on. Without a pty, no
+        // SIGHUP is sent to the remote command when this connection closes:
+        // it only sees its stdin reach EOF and its stdout/stderr break.
+        args.push("-o".to_string());
+        args.push("BatchMode=yes".to_string());
+        args.push(self.ssh_destination());
+        args
+    }
+}
+
+/// Builds the `tokio::process::Command` `spawn_internal` group-spawns. Local
+/// and SSH implementations return a `Command` with the same shape (program
+/// args already resolved to a single process to launch), so the caller
+/// doesn't need to know which one it got.
+pub trait ProcessTransport: Send + Sync {
+    /// Build a ready-to-spawn command for `program` with `args`, rooted at
+    /// `current_dir`, with `env`/`cmd_overrides` applied and `extra_env`
+    /// (e.g. secrets granted via the secret-request hook) layered on top.
+    /// `extra_env` is never placed on this command's argv or in a remote
+    /// exec string: see `stdin_env_preamble` for how a transport that can't
+    /// apply it directly (SSH) still gets it across.
+    fn build_command(
+        &self,
+        program: &str,
+        args: &[String],
+        current_dir: &Path,
+        env: &ExecutionEnv,
+        cmd_overrides: &CmdOverrides,
+        extra_env: &HashMap<String, String>,
+    ) -> Result<Command, ExecutorError>;
+
+    /// Lines `spawn_internal` must write to the child's stdin, before any
+    /// control-protocol traffic, to get `extra_env` into its environment.
+    /// Empty when `build_command` already applied `extra_env` directly (it
+    /// never touches this process's own argv either way, so there's nothing
+    /// to leak via `ps`/`/proc/<pid>/cmdline` on this host).
+    fn stdin_env_preamble(&self, extra_env: &HashMap<String, String>) -> Vec<String>;
+}
+
+/// Spawns the executor on the local machine, the behavior every executor had
+/// before remote transports existed.
+pub struct LocalTransport;
+
+impl ProcessTransport for LocalTransport {
+    fn build_command(
+        &self,
+        program: &str,
+        args: &[String],
+        current_dir: &Path,
+        env: &ExecutionEnv,
+        cmd_overrides: &CmdOverrides,
+        extra_env: &HashMap<String, String>,
+    ) -> Result<Command, ExecutorError> {
+        let mut command = Command::new(program);
+        command.current_dir(current_dir).args(args);
+        env.clone().with_profile(cmd_overrides).apply_to_command(&mut command);
+        command.envs(extra_env);
+        Ok(command)
+    }
+
+    fn stdin_env_preamble(&self, _extra_env: &HashMap<String, String>) -> Vec<String> {
+        // Applied directly above via `Command::envs`, which sets the
+        // child's environment and puts nothing on any argv that `ps` can
+        // read.
+        Vec::new()
+    }
+}
+
+/// Spawns the executor on a remote host over SSH. The local child we track
+/// is the `ssh` client itself: killing it (via `kill_on_drop`/process-group
+/// signal) drops the connection; no signal is sent to the remote process,
+/// which is left to exit once its stdin closes. The ssh client's stdio pipes
+/// are the remote process's stdio pipes.
+pub struct SshTransport {
+    target: RemoteTarget,
+}
+
+impl SshTransport {
+    pub fn new(target: RemoteTarget) -> Self {
+        Self { target }
+    }
+
+    /// Whether `program` answers on the remote host, used directly by the
+    /// (non-async) `get_availability_info` for a remote-configured run —
+    /// there's no separate async probe path to keep in sync with this one.
+    pub fn probe_available_blocking(&self, program: &str) -> bool {
+        std::process::Command::new("ssh")
+            .args(self.target.connection_args())
+            .arg(format!("{program} --version"))
+            .output()
+            .map(|output| output.status.success())
+            .unwrap_or(false)
+    }
+
+    /// Resolves the env vars `env`/`cmd_overrides` would set on a local
+    /// invocation, so the remote command can `export` the same values
+    /// instead of silently losing them. Deliberately excludes `extra_env`:
+    /// that's where granted secrets travel, and baking those into this exec
+    /// string would put their plaintext on the local `ssh` child's argv
+    /// (`/proc/<pid>/cmdline`, `ps -ef`) and in the remote shell invocation
+    /// for as long as the session runs. See `stdin_env_preamble` for how
+    /// `extra_env` crosses instead.
+    fn resolve_env_exports(env: &ExecutionEnv, cmd_overrides: &CmdOverrides) -> Vec<String> {
+        let mut probe = Command::new("true");
+        env.clone().with_profile(cmd_overrides).apply_to_command(&mut probe);
+        probe
+            .as_std()
+            .get_envs()
+            .filter_map(|(key, value)| {
+                value.map(|value| {
+                    format!(
+                        "export {}={};",
+                        key.to_string_lossy(),
+                        shell_escape(&value.to_string_lossy())
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// Name the preamble loop reads off stdin into `export "$line"` before
+    /// handing off to `program`, one `NAME=VALUE` per line, blank line to
+    /// end. Only `build_command` uses it; `stdin_env_preamble` just emits
+    /// the `NAME=VALUE` lines that loop reads.
+    const STDIN_ENV_SENTINEL_VAR: &str = "__vk_env_line";
+}
+
+impl ProcessTransport for SshTransport {
+    fn build_command(
+        &self,
+        program: &str,
+        args: &[String],
+        current_dir: &Path,
+        env: &ExecutionEnv,
+        cmd_overrides: &CmdOverrides,
+        extra_env: &HashMap<String, String>,
+    ) -> Result<Command, ExecutorError> {
+        let mut command = Command::new("ssh");
+        command.args(self.target.connection_args());
+
+        let exports = Self::resolve_env_exports(env, cmd_overrides).join(" ");
+        let remote_dir = shell_escape(&current_dir.display().to_string());
+        let remote_args = args.iter().map(|arg| shell_escape(arg)).collect::<Vec<_>>().join(" ");
+        let exec = format!("exec {program} {remote_args}");
+
+        let remote_command = if extra_env.is_empty() {
+            format!("{exports} cd {remote_dir} && {exec}")
+        } else {
+            // Block on a short-lived env preamble over stdin before the real
+            // control-protocol traffic starts: `spawn_internal` writes the
+            // matching `NAME=VALUE` lines (see `stdin_env_preamble`) ahead
+            // of anything else it sends to this command's stdin.
+            let var = Self::STDIN_ENV_SENTINEL_VAR;
+            format!(
+                "{exports} cd {remote_dir} && while IFS= read -r {var}; do [ -z \"${var}\" ] && break; export \"${var}\"; done && {exec}"
+            )
+        };
+        command.arg(remote_command);
+
+        Ok(command)
+    }
+
+    fn stdin_env_preamble(&self, extra_env: &HashMap<String, String>) -> Vec<String> {
+        extra_env.iter().map(|(key, value)| format!("{key}={value}")).collect()
+    }
+}
+
+fn shell_escape(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', "'\\''"))
+}
+
+/// Picks the transport for a spawn based on whether a remote target was
+/// configured on the executor.
+pub fn transport_for(remote: Option<&RemoteTarget>) -> Box<dyn ProcessTransport> {
+    match remote {
+        Some(target) => Box::new(SshTransport::new(target.clone())),
+        None => Box::new(LocalTransport),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shell_escape_wraps_plain_args() {
+        assert_eq!(shell_escape("hello"), "'hello'");
+    }
+
+    #[test]
+    fn shell_escape_handles_embedded_quotes() {
+        assert_eq!(shell_escape("it's"), "'it'\\''s'");
+    }
+
+    #[test]
+    fn connection_args_include_port_and_identity() {
+        let target = RemoteTarget {
+            host: "example.com".to_string(),
+            user: Some("vk".to_string()),
+            port: Some(2222),
+            identity_file: Some("/home/vk/.ssh/id_ed25519".to_string()),
+        };
+        let args = target.connection_args();
+        assert_eq!(
+            args,
+            vec![
+                "-p".to_string(),
+                "2222".to_string(),
+                "-i".to_string(),
+                "/home/vk/.ssh/id_ed25519".to_string(),
+                "-o".to_string(),
+                "BatchMode=yes".to_string(),
+                "vk@example.com".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn connection_args_omit_unset_fields() {
+        let target = RemoteTarget {
+            host: "example.com".to_string(),
+            user: None,
+            port: None,
+            identity_file: None,
+        };
+        assert_eq!(
+            target.connection_args(),
+            vec!["-o".to_string(), "BatchMode=yes".to_string(), "example.com".to_string()]
+        );
+    }
+
+    #[test]
+    fn local_transport_has_no_stdin_preamble() {
+        let extra_env = HashMap::from([("SECRET".to_string(), "value".to_string())]);
+        assert!(LocalTransport.stdin_env_preamble(&extra_env).is_empty());
+    }
+
+    #[test]
+    fn ssh_transport_stdin_preamble_carries_extra_env() {
+        let transport = SshTransport::new(RemoteTarget {
+            host: "example.com".to_string(),
+            user: None,
+            port: None,
+            identity_file: None,
+        });
+        let extra_env = HashMap::from([("TOKEN".to_string(), "s3cr3t".to_string())]);
+        assert_eq!(transport.stdin_env_preamble(&extra_env), vec!["TOKEN=s3cr3t".to_string()]);
+    }
+}